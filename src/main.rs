@@ -2,6 +2,8 @@ use derive_error::Error;
 use im::{Vector, OrdMap};
 use lazy_static::lazy_static;
 use regex::Regex;
+use serde::Serialize;
+use trie_rs::{Trie, TrieBuilder};
 use std::collections::HashMap;
 use std::io::BufRead;
 use std::path::PathBuf;
@@ -17,7 +19,95 @@ struct Opt {
 #[derive(StructOpt, Debug)]
 enum Command {
     Version,
-    VerifyRebaseInteractive { script_file: PathBuf, },
+    VerifyRebaseInteractive {
+        script_file: PathBuf,
+        /// Git access layer to use: `git2` (default) or `gix`/gitoxide.
+        #[structopt(long, default_value = "git2")]
+        backend: Backend,
+        /// Output format: `human` (default) or `json` for editor/CI integration.
+        #[structopt(long, default_value = "human")]
+        format: Format,
+        /// Restrict verification to files under the given path prefix. Repeatable;
+        /// when absent the whole tree is verified.
+        #[structopt(long = "path")]
+        paths: Vec<String>,
+    },
+}
+
+/// A prefix trie over the `--path` prefixes. A file is included when one of the
+/// requested prefixes is a prefix of its path ending on a path-segment
+/// boundary, found by a common-prefix search of the trie that walks the path
+/// once. An empty set matches everything.
+struct PathFilter {
+    trie: Option<Trie<u8>>,
+}
+
+impl PathFilter {
+    fn new(prefixes: &[String]) -> Self {
+        if prefixes.is_empty() {
+            return PathFilter { trie: None };
+        }
+        let mut builder = TrieBuilder::new();
+        for prefix in prefixes {
+            builder.push(prefix);
+        }
+        PathFilter { trie: Some(builder.build()) }
+    }
+
+    fn includes(&self, path: &str) -> bool {
+        match &self.trie {
+            None => true,
+            Some(trie) => {
+                // A byte-prefix match only counts when it lands on a path-segment
+                // boundary, so `--path src` selects `src` and `src/x` but not
+                // `srcfoo.rs` or `src-gen/…`.
+                let matches: Vec<String> = trie.common_prefix_search(path);
+                matches.iter().any(|prefix| {
+                    prefix.len() == path.len()
+                        || prefix.ends_with('/')
+                        || path.as_bytes().get(prefix.len()) == Some(&b'/')
+                })
+            }
+        }
+    }
+}
+
+/// How the verifier reports its per-commit results.
+#[derive(Clone, Copy, Debug)]
+enum Format {
+    Human,
+    Json,
+}
+
+impl std::str::FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Format::Human),
+            "json" => Ok(Format::Json),
+            _ => Err(Error::Backend(format!("unknown format: {}", s))),
+        }
+    }
+}
+
+/// Which git implementation backs the repository operations.
+#[derive(Clone, Copy, Debug)]
+enum Backend {
+    Git2,
+    Gix,
+}
+
+impl std::str::FromStr for Backend {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "git2" => Ok(Backend::Git2),
+            "gix" | "gitoxide" => Ok(Backend::Gix),
+            _ => Err(Error::Backend(format!("unknown backend: {}", s))),
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -26,23 +116,30 @@ pub enum Error {
     Io(std::io::Error),
     UniDiff(unidiff::Error),
     Git2(git2::Error),
+    Backend(String),
+    Json(serde_json::Error),
     NonMonotonicPatchLines,
     NotScriptFile,
 }
 
 type LineNr = usize;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 enum FileKind {
     Addition,
     Changes,
     Deletion,
+    Rename { from: String, to: String },
 }
 
 #[derive(Clone, Default, Debug)]
 struct Hunk {
     source: Vector<Rc<String>>,
     target: Vector<Rc<String>>,
+    /// For each line in `source`, whether it is a context line (also present in
+    /// `target`) rather than an actual removal. Used by `apply_hunks` to decide
+    /// which leading/trailing lines may be trimmed as patch(1) fuzz.
+    source_context: Vector<bool>,
 }
 
 #[derive(Clone, Debug)]
@@ -59,7 +156,12 @@ struct ChangeSet {
     files: OrdMap<String, FileInfo>,
 }
 
-fn add_commit_text(githash: &Option<String>, lines: &String) -> Result<ChangeSet, Error> {
+fn add_commit_text(
+    githash: &Option<String>,
+    lines: &String,
+    renames: &HashMap<String, String>,
+    filter: &PathFilter,
+) -> Result<ChangeSet, Error> {
     let empty_line = Rc::new(String::new());
     if let Some(githash) = githash {
         let mut files = OrdMap::new();
@@ -68,7 +170,13 @@ fn add_commit_text(githash: &Option<String>, lines: &String) -> Result<ChangeSet
         ps.parse(lines)?;
 
         for file in ps.files() {
-            let (normalization, kind) = if file.is_added_file() {
+            let path = file.path();
+            if !filter.includes(&path) {
+                continue;
+            }
+            let (normalization, kind) = if let Some(from) = renames.get(&path) {
+                (1, FileKind::Rename { from: from.clone(), to: path.clone() })
+            } else if file.is_added_file() {
                 (0, FileKind::Addition)
             } else if file.is_removed_file() {
                 (0, FileKind::Deletion)
@@ -89,17 +197,20 @@ fn add_commit_text(githash: &Option<String>, lines: &String) -> Result<ChangeSet
                         unidiff::LINE_TYPE_REMOVED => {
                             let rc = Rc::new(line.value.clone());
                             new_hunk.source.push_back(rc);
+                            new_hunk.source_context.push_back(false);
                         }
                         unidiff::LINE_TYPE_CONTEXT => {
                             let rc = Rc::new(line.value.clone());
                             new_hunk.source.push_back(rc.clone());
                             new_hunk.target.push_back(rc);
+                            new_hunk.source_context.push_back(true);
                         }
                         unidiff::LINE_TYPE_EMPTY => {
                             new_hunk.source.push_back(
                                 empty_line.clone());
                             new_hunk.target.push_back(
                                 empty_line.clone());
+                            new_hunk.source_context.push_back(true);
                         }
                         _ => panic!(),
                     }
@@ -107,7 +218,7 @@ fn add_commit_text(githash: &Option<String>, lines: &String) -> Result<ChangeSet
                 hunks.push_back((hunk.source_start - normalization, new_hunk));
             }
 
-            files.insert(file.path(), FileInfo {
+            files.insert(path, FileInfo {
                 kind,
                 hunks,
             });
@@ -122,85 +233,159 @@ fn add_commit_text(githash: &Option<String>, lines: &String) -> Result<ChangeSet
     panic!();
 }
 
-#[derive(Debug)]
-enum MergeError {
-    UnappliedHunk(u32),
+/// A materialized conflict, left in the file content surrounded by
+/// `<<<<<<< base` / `=======` / `>>>>>>> <githash>` markers, the same way an
+/// interactive rebase leaves the worktree when a hunk does not apply. A hunk
+/// against a file that was already removed is recorded without markers, as
+/// there is no content to splice them into.
+#[derive(Clone, Debug)]
+struct Conflict {
+    path: String,
+    source_start: LineNr,
+    /// The fuzz offset (in lines) between where the hunk expected its source and
+    /// where it was actually materialized; zero when spliced at the expected
+    /// position.
+    offset: isize,
 }
 
-#[derive(Debug)]
-struct MergeErrors {
-    list: Vec<(String, MergeError)>,
+/// A hunk that applied only after ignoring some context lines, recorded so the
+/// output can warn "applied with fuzz N" the way GNU patch does.
+#[derive(Clone, Debug)]
+struct AppliedHunk {
+    path: String,
+    source_start: LineNr,
+    fuzz: usize,
+    offset: isize,
 }
 
+/// A backend-neutral object identifier (the hex oid). Both the git2 and the
+/// gix backends resolve it back into their own oid type on lookup.
+type ObjId = String;
+
+#[derive(Clone)]
 enum FileState {
-    Oid(git2::Oid),
+    Oid(ObjId),
     Removed,
     Loaded(Vector<Rc<String>>),
 }
 
 type FileSet = HashMap<String, FileState>;
 
-fn apply_hunks(file_info: &FileInfo, content: &mut Vector<Rc<String>>) -> Result<(), MergeError> {
+/// Number of leading and trailing context lines in a hunk's `source` — the only
+/// lines patch(1) is allowed to ignore when applying with fuzz.
+fn context_runs(hunk: &Hunk) -> (usize, usize) {
+    let lead = hunk.source_context.iter().take_while(|c| **c).count();
+    let trail = hunk.source_context.iter().rev().take_while(|c| **c).count();
+    (lead, trail)
+}
+
+fn apply_hunks(
+    githash: &GitRef,
+    path: &str,
+    file_info: &FileInfo,
+    content: &mut Vector<Rc<String>>,
+    conflicts: &mut Vec<Conflict>,
+    applied: &mut Vec<AppliedHunk>,
+) {
     let mut source_diff = 0isize;
 
     for (source_line, hunk) in &file_info.hunks {
         let pivot_line = (*source_line as isize).saturating_add(source_diff) as usize;
-        let mut distance = 0isize;
-        let mut fuzz = None;
-
-        'found: while distance < content.len() as isize {
-            let two_places = [distance, -distance];
-            let places = if distance == 0 {
-                &[0][..]
-            } else {
-                &two_places[..]
-            };
+        let s_l = hunk.source.len();
+        let (lead_ctx, trail_ctx) = context_runs(hunk);
+
+        // Try fuzz 0 (an exact match of the whole source) first, then retry with
+        // fuzz F=1..=3, ignoring up to F leading and up to F trailing context
+        // lines. Trimmed positions are treated as wildcards and the match is
+        // anchored on the interior lines, which always include every removal.
+        let mut matched = None;
+        'fuzz: for f in 0..=3usize {
+            let trim_lead = f.min(lead_ctx);
+            let trim_trail = f.min(trail_ctx);
+            let anchor_len = s_l.saturating_sub(trim_lead + trim_trail);
+            if anchor_len == 0 && f > 0 {
+                // Trimming left fewer than one anchor line: fall through to the
+                // next fuzz level (and ultimately to a conflict). At fuzz 0 an
+                // empty source is a pure insertion and still matches at the pivot.
+                continue;
+            }
 
-            'next: for place in places.into_iter() {
-                let v = if *place < 0 {
-                    if pivot_line as isize >= -*place {
-                        Some((pivot_line as isize + *place) as usize)
-                    } else {
-                        None
-                    }
-                } else if *place > 0 {
-                    if pivot_line + *place as usize +
-                        hunk.source.len() > content.len()
-                    {
-                        None
-                    } else {
-                        Some(pivot_line + *place as usize)
-                    }
+            let mut distance = 0isize;
+            while distance <= content.len() as isize {
+                let two_places = [distance, -distance];
+                let places = if distance == 0 {
+                    &[0][..]
                 } else {
-                    Some(pivot_line)
+                    &two_places[..]
                 };
 
-                if let Some(v) = v {
-                    for line in 0 ..  hunk.source.len() {
-                        if hunk.source[line] != content[line + v] {
+                'next: for place in places.into_iter() {
+                    let v = pivot_line as isize + *place;
+                    if v < 0 || v as usize + s_l > content.len() {
+                        continue 'next;
+                    }
+                    let v = v as usize;
+
+                    for line in 0..anchor_len {
+                        if hunk.source[trim_lead + line] != content[v + trim_lead + line] {
                             continue 'next;
                         }
                     }
 
-                    fuzz = Some(v as isize - pivot_line as isize);
-                    break 'found;
+                    matched = Some((v, f, v as isize - pivot_line as isize));
+                    break 'fuzz;
                 }
-            }
 
-            distance += 1;
+                distance += 1;
+            }
         }
 
-        let pos = match fuzz {
+        let pos = match matched {
             None => {
-                return Err(MergeError::UnappliedHunk(*source_line as u32));
+                // The hunk's source could not be located anywhere in the fuzz
+                // window. Rather than abort, splice a materialized conflict into
+                // the content at the expected position so later hunks still have
+                // something to line up against, and record it.
+                let at = (pivot_line.min(content.len())) as usize;
+
+                let mut marker = Vector::new();
+                marker.push_back(Rc::new(String::from("<<<<<<< base")));
+                marker.append(hunk.source.clone());
+                marker.push_back(Rc::new(String::from("=======")));
+                marker.append(hunk.target.clone());
+                marker.push_back(Rc::new(format!(">>>>>>> {}", githash)));
+
+                let inserted = marker.len();
+                let after = content.split_off(at);
+                content.append(marker);
+                content.append(after);
+
+                source_diff += inserted as isize;
+
+                conflicts.push(Conflict {
+                    path: path.to_owned(),
+                    source_start: *source_line,
+                    // Offset between where the hunk expected its source and where
+                    // it was materialized: zero at the pivot, non-zero only when
+                    // the pivot was clamped to the end of a shorter file.
+                    offset: at as isize - pivot_line as isize,
+                });
+                continue;
             }
-            Some(v) => {
-                (v + pivot_line as isize) as usize
+            Some((v, fuzz, offset)) => {
+                if fuzz > 0 {
+                    applied.push(AppliedHunk {
+                        path: path.to_owned(),
+                        source_start: *source_line,
+                        fuzz,
+                        offset,
+                    });
+                }
+                v
             }
         };
 
         let t_l = hunk.target.len();
-        let s_l = hunk.source.len();
 
         let part = content.split_off(pos);
         let (_, after) = part.split_at(s_l);
@@ -210,34 +395,53 @@ fn apply_hunks(file_info: &FileInfo, content: &mut Vector<Rc<String>>) -> Result
         source_diff += t_l as isize;
         source_diff -= s_l as isize;
     }
-
-    Ok(())
 }
 
-fn apply(repo: &git2::Repository, fs: &mut FileSet, changeset: &ChangeSet) -> Result<(), MergeErrors>
-{
-    let mut merge_errors = MergeErrors {
-        list: vec![],
-    };
+/// Resolve a file's `Oid` state into a loaded line vector so hunks can be
+/// applied against it. A no-op for states that are already loaded or removed.
+fn load_content(backend: &dyn RepoBackend, fs: &mut FileSet, path: &str) {
+    if let Some(file) = fs.get_mut(path) {
+        if let FileState::Oid(oid) = &file {
+            let content = backend.blob_content(oid).unwrap();
+            let mut vector = Vector::new();
+            for line in content.lines() {
+                vector.push_back(Rc::new(String::from(line.unwrap())))
+            }
+            *file = FileState::Loaded(vector);
+        }
+    }
+}
 
+fn apply(
+    backend: &dyn RepoBackend,
+    fs: &mut FileSet,
+    changeset: &ChangeSet,
+    conflicts: &mut Vec<Conflict>,
+    applied: &mut Vec<AppliedHunk>,
+    filter: &PathFilter,
+    human: bool,
+) {
     for (path, file_info) in &changeset.files {
-        println!("  {}", path);
-        if let Some(file) = fs.get_mut(path) {
-            match &file {
-                FileState::Oid(oid) => {
-                    let blob = repo.find_blob(*oid);
-                    let content = blob.as_ref().unwrap().content();
-                    let mut vector = Vector::new();
-                    for line in content.lines() {
-                        vector.push_back(Rc::new(String::from(line.unwrap())))
-                    }
-                    *file = FileState::Loaded(vector);
-                }
-                _ => {}
+        if !filter.includes(path) {
+            continue;
+        }
+        if human {
+            println!("  {}", path);
+        }
+
+        // A rename moves the existing file state (loading its content first) to
+        // the new key so the change hunks below apply against the content the
+        // downstream commits expect to find at the new path.
+        if let FileKind::Rename { from, to } = &file_info.kind {
+            load_content(backend, fs, from);
+            if let Some(state) = fs.remove(from) {
+                fs.insert(to.clone(), state);
             }
+        } else {
+            load_content(backend, fs, path);
         }
 
-        match file_info.kind {
+        match &file_info.kind {
             FileKind::Addition => {
                 for hunk in &file_info.hunks {
                     if let Some(file) = fs.get_mut(path) {
@@ -256,50 +460,327 @@ fn apply(repo: &git2::Repository, fs: &mut FileSet, changeset: &ChangeSet) -> Re
                     *file = FileState::Removed;
                 }
             }
-            FileKind::Changes => {
+            FileKind::Changes | FileKind::Rename { .. } => {
                 if let Some(mut file) = fs.get_mut(path) {
                     match &mut file {
                         FileState::Oid(_) => panic!(),
-                        FileState::Removed => todo!(),
-                        FileState::Loaded(content) => {
-                            if let Err(err) = apply_hunks(&file_info, content) {
-                                merge_errors.list.push((path.clone(), err));
+                        FileState::Removed => {
+                            // The path was deleted earlier in the plan (e.g. a
+                            // reordered delete-then-modify): there is nothing to
+                            // apply against, so record a conflict per hunk rather
+                            // than abort.
+                            for (source_line, _) in &file_info.hunks {
+                                conflicts.push(Conflict {
+                                    path: path.to_owned(),
+                                    source_start: *source_line,
+                                    offset: 0,
+                                });
                             }
                         }
+                        FileState::Loaded(content) => {
+                            apply_hunks(&changeset.githash, path, &file_info, content, conflicts, applied);
+                        }
                     }
                 }
             }
         }
     }
+}
 
-    if merge_errors.list.len() > 0 {
-        return Err(merge_errors);
+/// The set of git operations the verifier needs, factored out so it can run
+/// against either `git2` or a pure-`gix`/gitoxide repository (and, in principle,
+/// an in-memory fake for testing `apply_hunks`).
+trait RepoBackend {
+    /// Walk the tree of `rev` into a `FileSet` of blob states keyed by path.
+    fn commit_to_fileset(&self, rev: &str) -> Result<FileSet, Error>;
+
+    /// Load a blob's raw bytes by its (hex) object id.
+    fn blob_content(&self, oid: &ObjId) -> Result<Vec<u8>, Error>;
+
+    /// Produce unified diff text for `rev` against its first parent, along with
+    /// the detected rename map (new path -> old path).
+    fn commit_diff(&self, rev: &str) -> Result<(String, HashMap<String, String>), Error>;
+}
+
+fn open_backend(kind: Backend, repo_path: &str) -> Result<Box<dyn RepoBackend>, Error> {
+    match kind {
+        Backend::Git2 => Ok(Box::new(Git2Backend::open(repo_path)?)),
+        Backend::Gix => Ok(Box::new(GixBackend::open(repo_path)?)),
     }
+}
 
-    Ok(())
+struct Git2Backend {
+    repo: git2::Repository,
 }
 
-fn commit_to_fileset(obj: git2::Object) -> Result<FileSet, Error> {
-    let mut blobs = std::collections::HashMap::new();
+impl Git2Backend {
+    fn open(repo_path: &str) -> Result<Self, Error> {
+        Ok(Git2Backend { repo: git2::Repository::open(repo_path)? })
+    }
+}
+
+impl RepoBackend for Git2Backend {
+    fn commit_to_fileset(&self, rev: &str) -> Result<FileSet, Error> {
+        let mut blobs = HashMap::new();
+
+        let obj = self.repo.revparse_single(rev)?;
+        if let Some(commit) = obj.as_commit() {
+            if let Ok(tree) = commit.tree() {
+                tree.walk(git2::TreeWalkMode::PreOrder, |v, entry| {
+                    if let Some(name) = entry.name() {
+                        if let Some(git2::ObjectType::Blob) = entry.kind() {
+                            let path = format!("{}{}", v, name);
+                            blobs.insert(path, FileState::Oid(entry.id().to_string()));
+                        }
+                    }
+                    git2::TreeWalkResult::Ok
+                })?;
+            }
+        }
+
+        Ok(blobs)
+    }
+
+    fn blob_content(&self, oid: &ObjId) -> Result<Vec<u8>, Error> {
+        let oid = git2::Oid::from_str(oid)?;
+        let blob = self.repo.find_blob(oid)?;
+        Ok(blob.content().to_vec())
+    }
+
+    fn commit_diff(&self, rev: &str) -> Result<(String, HashMap<String, String>), Error> {
+        let obj = self.repo.revparse_single(rev)?;
+        let tree = obj.peel_to_tree()?;
+        let commit = obj.peel_to_commit()?;
+
+        let parents: Vec<_> = commit.parents().collect();
+        let parent = parents.first().unwrap();
+        let parent_tree = parent.tree()?;
+
+        let mut diff = self.repo.diff_tree_to_tree(Some(&parent_tree), Some(&tree), None)?;
 
-    if let Some(parent_commit) = obj.as_commit() {
-        if let Ok(tree) = parent_commit.tree() {
-            tree.walk(git2::TreeWalkMode::PreOrder, |v, entry| {
-                if let Some(name) = entry.name() {
-                    if let Some(git2::ObjectType::Blob) = entry.kind() {
-                        let path = format!("{}{}", v, name);
-                        blobs.insert(path, FileState::Oid(entry.id()));
+        let mut find_opts = git2::DiffFindOptions::new();
+        find_opts.renames(true).copies(true);
+        diff.find_similar(Some(&mut find_opts))?;
+
+        let mut renames = HashMap::new();
+        for delta in diff.deltas() {
+            match delta.status() {
+                git2::Delta::Renamed | git2::Delta::Copied => {
+                    if let (Some(from), Some(to)) =
+                        (delta.old_file().path(), delta.new_file().path())
+                    {
+                        renames.insert(
+                            to.to_string_lossy().into_owned(),
+                            from.to_string_lossy().into_owned(),
+                        );
                     }
                 }
-                git2::TreeWalkResult::Ok
-            })?;
+                _ => {}
+            }
+        }
+
+        let mut s = Vec::new();
+        diff.print(git2::DiffFormat::Patch, |_, _, l| {
+            match l.origin() {
+                '+' | '-' | ' ' => s.push(l.origin() as u8),
+                _ => {}
+            }
+            s.extend(l.content());
+            true
+        })?;
+
+        Ok((String::from_utf8_lossy(&s).into_owned(), renames))
+    }
+}
+
+struct GixBackend {
+    repo: gix::Repository,
+}
+
+impl GixBackend {
+    fn open(repo_path: &str) -> Result<Self, Error> {
+        let repo = gix::open(repo_path).map_err(|e| Error::Backend(e.to_string()))?;
+        Ok(GixBackend { repo })
+    }
+
+    fn tree_of(&self, rev: &str) -> Result<gix::Tree, Error> {
+        let id = self.repo.rev_parse_single(rev).map_err(|e| Error::Backend(e.to_string()))?;
+        let commit = id
+            .object()
+            .map_err(|e| Error::Backend(e.to_string()))?
+            .try_into_commit()
+            .map_err(|e| Error::Backend(e.to_string()))?;
+        commit.tree().map_err(|e| Error::Backend(e.to_string()))
+    }
+}
+
+impl RepoBackend for GixBackend {
+    fn commit_to_fileset(&self, rev: &str) -> Result<FileSet, Error> {
+        let mut blobs = HashMap::new();
+
+        let tree = self.tree_of(rev)?;
+        let mut recorder = gix::traverse::tree::Recorder::default();
+        tree.traverse()
+            .breadthfirst(&mut recorder)
+            .map_err(|e| Error::Backend(e.to_string()))?;
+
+        for entry in recorder.records {
+            if entry.mode.is_blob() {
+                blobs.insert(entry.filepath.to_string(), FileState::Oid(entry.oid.to_string()));
+            }
         }
+
+        Ok(blobs)
+    }
+
+    fn blob_content(&self, oid: &ObjId) -> Result<Vec<u8>, Error> {
+        let id = gix::ObjectId::from_hex(oid.as_bytes()).map_err(|e| Error::Backend(e.to_string()))?;
+        let object = self.repo.find_object(id).map_err(|e| Error::Backend(e.to_string()))?;
+        let blob = object.try_into_blob().map_err(|e| Error::Backend(e.to_string()))?;
+        Ok(blob.data.clone())
+    }
+
+    fn commit_diff(&self, rev: &str) -> Result<(String, HashMap<String, String>), Error> {
+        let id = self.repo.rev_parse_single(rev).map_err(|e| Error::Backend(e.to_string()))?;
+        let commit = id
+            .object()
+            .map_err(|e| Error::Backend(e.to_string()))?
+            .try_into_commit()
+            .map_err(|e| Error::Backend(e.to_string()))?;
+        let tree = commit.tree().map_err(|e| Error::Backend(e.to_string()))?;
+
+        let parent_id = commit
+            .parent_ids()
+            .next()
+            .ok_or_else(|| Error::Backend(format!("{} has no parent", rev)))?;
+        let parent_tree = parent_id
+            .object()
+            .map_err(|e| Error::Backend(e.to_string()))?
+            .try_into_commit()
+            .map_err(|e| Error::Backend(e.to_string()))?
+            .tree()
+            .map_err(|e| Error::Backend(e.to_string()))?;
+
+        let mut patch = String::new();
+        let mut renames = HashMap::new();
+
+        let blob_data = |id: gix::Id| -> Result<Vec<u8>, Error> {
+            let object = id.object().map_err(|e| Error::Backend(e.to_string()))?;
+            let blob = object.try_into_blob().map_err(|e| Error::Backend(e.to_string()))?;
+            Ok(blob.data.clone())
+        };
+
+        let mut changes = parent_tree.changes().map_err(|e| Error::Backend(e.to_string()))?;
+        changes
+            .track_rewrites(Some(Default::default()))
+            .for_each_to_obtain_tree(&tree, |change| {
+                use gix::object::tree::diff::Change;
+                // Emit real unified-diff text: `/dev/null` headers for pure
+                // add/delete so `is_added_file`/`is_removed_file` classify them,
+                // and a full `@@` hunk body so the content actually applies.
+                match change {
+                    Change::Addition { location, entry_mode, id, .. } => {
+                        if entry_mode.is_blob() {
+                            let new = blob_data(id)?;
+                            patch.push_str(&format!("--- /dev/null\n+++ b/{}\n", location));
+                            patch.push_str(&unified_diff(&[], &new));
+                        }
+                    }
+                    Change::Deletion { location, entry_mode, id, .. } => {
+                        if entry_mode.is_blob() {
+                            let old = blob_data(id)?;
+                            patch.push_str(&format!("--- a/{}\n+++ /dev/null\n", location));
+                            patch.push_str(&unified_diff(&old, &[]));
+                        }
+                    }
+                    Change::Modification { location, previous_id, id, .. } => {
+                        let old = blob_data(previous_id)?;
+                        let new = blob_data(id)?;
+                        patch.push_str(&format!("--- a/{}\n+++ b/{}\n", location, location));
+                        patch.push_str(&unified_diff(&old, &new));
+                    }
+                    Change::Rewrite { source_location, location, source_id, id, .. } => {
+                        renames.insert(location.to_string(), source_location.to_string());
+                        let old = blob_data(source_id)?;
+                        let new = blob_data(id)?;
+                        patch.push_str(&format!("--- a/{}\n+++ b/{}\n", source_location, location));
+                        patch.push_str(&unified_diff(&old, &new));
+                    }
+                }
+                Ok::<_, Error>(gix::object::tree::diff::Action::Continue)
+            })
+            .map_err(|e| Error::Backend(e.to_string()))?;
+
+        Ok((patch, renames))
     }
+}
+
+/// Render a GNU-style unified-diff body (the `@@` hunk headers and `+/-/ `
+/// content lines) for two blobs using gitoxide's imara-diff, so the gix backend
+/// produces patch text `unidiff` can parse the same way git2's does.
+fn unified_diff(old: &[u8], new: &[u8]) -> String {
+    use gix::diff::blob::intern::InternedInput;
+    use gix::diff::blob::{Algorithm, UnifiedDiffBuilder};
+
+    let old = String::from_utf8_lossy(old);
+    let new = String::from_utf8_lossy(new);
+    let input = InternedInput::new(old.as_ref(), new.as_ref());
+    gix::diff::blob::diff(Algorithm::Histogram, &input, UnifiedDiffBuilder::new(&input))
+}
+
+/// A single conflict as it appears in the JSON report.
+#[derive(Serialize)]
+struct ConflictReport {
+    path: String,
+    source_start: LineNr,
+    offset: isize,
+}
+
+/// A hunk that applied with fuzz, as it appears in the JSON report.
+#[derive(Serialize)]
+struct AppliedReport {
+    path: String,
+    source_start: LineNr,
+    fuzz: usize,
+    offset: isize,
+}
+
+/// One entry of the JSON report, mirroring a todo-script line.
+#[derive(Serialize)]
+struct CommitReport {
+    githash: GitRef,
+    line_nr: usize,
+    conflicts: Vec<ConflictReport>,
+    applied_with_fuzz: Vec<AppliedReport>,
+}
 
-    Ok(blobs)
+/// One command in an interactive (possibly `--rebase-merges`) todo script. Only
+/// `Pick` carries a changeset to apply; the rest drive the simulated worktree
+/// state so topic branches are rebuilt the way the real rebase would build them.
+enum Step {
+    /// pick/reword/squash/fixup — apply the commit's changeset.
+    Pick(ChangeSet),
+    /// drop — omit the commit entirely.
+    Drop(GitRef),
+    /// exec/break — no content effect; echoed in the human output only, the
+    /// JSON report has no entry for them.
+    Exec(String),
+    Break,
+    /// label — snapshot the current file set under a name.
+    Label(String),
+    /// reset — restore the file set previously snapshot under a name.
+    Reset(String),
+    /// merge -C <oid> <label> — re-merge a saved file set into the current one.
+    Merge { oid: Option<GitRef>, label: String },
 }
 
-fn verify_rebase_interactive(script_path: &PathBuf) -> Result<(), Error> {
+fn verify_rebase_interactive(
+    script_path: &PathBuf,
+    backend_kind: Backend,
+    format: Format,
+    paths: &[String],
+) -> Result<(), Error> {
+    let filter = PathFilter::new(paths);
     let suffix = "/rebase-merge/git-rebase-todo";
     let onto_suffix = "/rebase-merge/onto";
     let script_path_str = script_path.to_str().unwrap();
@@ -308,63 +789,169 @@ fn verify_rebase_interactive(script_path: &PathBuf) -> Result<(), Error> {
     }
 
     lazy_static! {
-        static ref RE: Regex = Regex::new("^ *(pick|reword|squash|fixup) ([^ ]+)").unwrap();
+        // A pick-like verb (one that applies a commit) or `drop`, plus its hash.
+        static ref PICK_RE: Regex =
+            Regex::new("^ *(pick|p|reword|r|squash|s|fixup|f|drop|d) ([^ ]+)").unwrap();
+        // `merge [-C|-c <oid>] <label>`; the oid group is optional.
+        static ref MERGE_RE: Regex =
+            Regex::new(r"^ *(?:merge|m)(?: -[Cc] ([^ ]+))? +([^ ]+)").unwrap();
+        // `label`/`reset` and their one-letter aliases, with a name.
+        static ref LABEL_RE: Regex = Regex::new("^ *(label|l|reset|t) +([^ ]+)").unwrap();
+        // `exec`/`break` and their aliases; exec keeps its command tail.
+        static ref EXEC_RE: Regex = Regex::new("^ *(?:exec|x) +(.*)").unwrap();
+        static ref BREAK_RE: Regex = Regex::new("^ *(break|b) *$").unwrap();
     }
 
     let repo_path = &script_path_str[..script_path_str.len() - suffix.len()];
     let rebase_onto = std::fs::read_to_string(&(repo_path.to_owned() + onto_suffix))?;
     let rebase_onto = rebase_onto.trim();
-    let repo = git2::Repository::open(&repo_path)?;
-    let obj = repo.revparse_single(&rebase_onto)?;
-    let mut fileset = commit_to_fileset(obj)?;
+    let backend = open_backend(backend_kind, repo_path)?;
+    let mut fileset = backend.commit_to_fileset(rebase_onto)?;
 
-    let mut commits = vec![];
+    let mut steps = vec![];
     for (line_nr, line) in std::io::BufReader::new(std::fs::File::open(script_path_str)?).lines().enumerate() {
-        if let Some(p) = RE.captures(&line?) {
-            if let Some(commit_hash) = p.get(2) {
-                let xgithash = String::from(commit_hash.as_str());
-                let obj = repo.revparse_single(&xgithash)?;
-                let tree = obj.peel_to_tree()?;
-                let commit = obj.peel_to_commit();
-
-                if let Ok(commit) = commit {
-                    let parents : Vec<_> = commit.parents().collect();
-                    let parent = parents.first().unwrap();
-                    let parent_tree = parent.tree()?;
-
-                    let diff = repo.diff_tree_to_tree(Some(&parent_tree), Some(&tree), None)?;
-                    let mut s = Vec::new();
-
-                    diff.print(git2::DiffFormat::Patch, |_, _, l| {
-                        match l.origin() {
-                            '+' | '-' | ' ' => s.push(l.origin() as u8),
-                            _ => {}
-                        }
-                        s.extend(l.content());
-                        true
-                    })?;
-
-                    let diff_text = String::from_utf8_lossy(&s).into_owned();
-                    let githash = Some(xgithash);
-                    commits.push((line_nr + 1, add_commit_text(&githash, &diff_text)?));
-                }
+        let line = line?;
+        let line_nr = line_nr + 1;
+        if let Some(p) = PICK_RE.captures(&line) {
+            let verb = p.get(1).unwrap().as_str();
+            let hash = String::from(p.get(2).unwrap().as_str());
+            if verb == "drop" || verb == "d" {
+                steps.push((line_nr, Step::Drop(hash)));
+            } else {
+                let (diff_text, renames) = backend.commit_diff(&hash)?;
+                let githash = Some(hash);
+                let changeset = add_commit_text(&githash, &diff_text, &renames, &filter)?;
+                steps.push((line_nr, Step::Pick(changeset)));
+            }
+        } else if let Some(p) = MERGE_RE.captures(&line) {
+            let oid = p.get(1).map(|m| m.as_str().to_owned());
+            let label = String::from(p.get(2).unwrap().as_str());
+            steps.push((line_nr, Step::Merge { oid, label }));
+        } else if let Some(p) = LABEL_RE.captures(&line) {
+            let verb = p.get(1).unwrap().as_str();
+            let name = String::from(p.get(2).unwrap().as_str());
+            if verb == "reset" || verb == "t" {
+                steps.push((line_nr, Step::Reset(name)));
+            } else {
+                steps.push((line_nr, Step::Label(name)));
             }
+        } else if let Some(p) = EXEC_RE.captures(&line) {
+            steps.push((line_nr, Step::Exec(String::from(p.get(1).unwrap().as_str()))));
+        } else if BREAK_RE.is_match(&line) {
+            steps.push((line_nr, Step::Break));
         }
     }
 
-    let nr_commits = commits.len();
-    for (index, (line_nr, commit)) in commits.into_iter().enumerate() {
-        println!("Processing [{}/{}]: {}", index + 1, nr_commits, commit.githash);
+    let nr_commits = steps
+        .iter()
+        .filter(|(_, s)| matches!(s, Step::Pick(_)))
+        .count();
+    let human = matches!(format, Format::Human);
+    // Pre-seed the reserved `onto` label git uses in `--rebase-merges` scripts
+    // so a `reset onto` restores the starting point without a revparse.
+    let mut labels: HashMap<String, FileSet> = HashMap::new();
+    labels.insert(String::from("onto"), fileset.clone());
+    let mut reports = vec![];
+    let mut index = 0;
+    for (line_nr, step) in steps {
+        match step {
+            Step::Pick(commit) => {
+                index += 1;
+                if human {
+                    println!("Processing [{}/{}]: {}", index, nr_commits, commit.githash);
+                }
+
+                let mut conflicts = vec![];
+                let mut applied = vec![];
+                apply(backend.as_ref(), &mut fileset, &commit, &mut conflicts, &mut applied, &filter, human);
 
-        match apply(&repo, &mut fileset, &commit) {
-            Err(err) => {
-                println!("{}:{}: error: {:?}", script_path_str, line_nr, err);
-                break;
+                if human {
+                    for hunk in &applied {
+                        println!(
+                            "{}:{}: warning: {} applied with fuzz {} (offset {} lines)",
+                            script_path_str, line_nr, hunk.path, hunk.fuzz, hunk.offset
+                        );
+                    }
+                    for conflict in &conflicts {
+                        println!(
+                            "{}:{}: conflict: {} (source line {})",
+                            script_path_str, line_nr, conflict.path, conflict.source_start
+                        );
+                    }
+                } else {
+                    reports.push(CommitReport {
+                        githash: commit.githash,
+                        line_nr,
+                        conflicts: conflicts
+                            .iter()
+                            .map(|c| ConflictReport {
+                                path: c.path.clone(),
+                                source_start: c.source_start,
+                                offset: c.offset,
+                            })
+                            .collect(),
+                        applied_with_fuzz: applied
+                            .iter()
+                            .map(|h| AppliedReport {
+                                path: h.path.clone(),
+                                source_start: h.source_start,
+                                fuzz: h.fuzz,
+                                offset: h.offset,
+                            })
+                            .collect(),
+                    });
+                }
+            }
+            Step::Drop(hash) => {
+                if human {
+                    println!("Dropping: {}", hash);
+                }
+            }
+            Step::Exec(cmd) => {
+                if human {
+                    println!("exec: {}", cmd);
+                }
+            }
+            Step::Break => {
+                if human {
+                    println!("break");
+                }
+            }
+            Step::Label(name) => {
+                labels.insert(name, fileset.clone());
+            }
+            Step::Reset(name) => {
+                // Restore a previously labelled state. An unknown label (one
+                // defined later, or a reserved name we did not seed) is warned
+                // about in the human output but must not abort the whole pass.
+                if let Some(saved) = labels.get(&name) {
+                    fileset = saved.clone();
+                } else if human {
+                    println!("{}:{}: warning: reset to unknown label {}", script_path_str, line_nr, name);
+                }
+            }
+            Step::Merge { oid, label } => {
+                if human {
+                    match &oid {
+                        Some(oid) => println!("Merging {} into {}", label, oid),
+                        None => println!("Merging {}", label),
+                    }
+                }
+                // Re-merge the saved topic-branch state by overlaying its files
+                // onto the current set, rebuilding the merge commit's tree.
+                if let Some(saved) = labels.get(&label) {
+                    for (path, state) in saved.clone() {
+                        fileset.insert(path, state);
+                    }
+                }
             }
-            Ok(()) => {}
         }
     }
 
+    if let Format::Json = format {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    }
+
     Ok(())
 }
 
@@ -375,8 +962,8 @@ fn main() -> Result<(), Error> {
         Command::Version => {
             println!("{}", env!("VERGEN_SHA"));
         }
-        Command::VerifyRebaseInteractive { script_file } => {
-            verify_rebase_interactive(script_file)?;
+        Command::VerifyRebaseInteractive { script_file, backend, format, paths } => {
+            verify_rebase_interactive(script_file, *backend, *format, paths)?;
         }
     }
 